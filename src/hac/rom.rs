@@ -12,6 +12,7 @@ use tracing::{debug, info};
 use walkdir::WalkDir;
 
 use crate::hac::backend::Backend;
+use crate::vfs::pfs0::Pfs0;
 
 use super::ticket::{self, TitleKey};
 
@@ -57,22 +58,11 @@ impl Nsp {
         })
     }
     pub fn extract_data_to<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let hactool = Backend::Hactool.path()?;
-
         info!("Extracting {:?}", &self.path);
-        if !Command::new(hactool)
-            .args([
-                "-t",
-                "pfs0",
-                "--pfs0dir",
-                &path.as_ref().to_string_lossy(),
-                &self.path.to_string_lossy(),
-            ])
-            .status()?
-            .success()
-        {
-            bail!("failed to extract {:?}", path.as_ref());
-        }
+
+        Pfs0::open(&self.path)
+            .and_then(|pfs0| pfs0.extract_to(path.as_ref()))
+            .with_context(|| format!("failed to extract {:?}", path.as_ref()))?;
 
         info!(
             "{:?} has been extracted in {:?}",
@@ -0,0 +1,253 @@
+//! https://switchbrew.org/wiki/NCA_Format#PFS0
+//!
+//! Native, in-process parser for the PFS0 ("PartitionFs") container format used by `.nsp`
+//! files, so listing/extracting entries no longer has to shell out to hactool.
+
+use std::{
+    fs,
+    path::{Component, Path},
+};
+
+use anyhow::{bail, Context, Result};
+use memmap2::{Mmap, MmapOptions};
+
+const MAGIC: &[u8; 4] = b"PFS0";
+const HEADER_SIZE: usize = 0x10;
+const ENTRY_SIZE: usize = 0x18;
+
+/// A single file entry in a PFS0 container.
+#[derive(Debug, Clone)]
+pub struct Pfs0Entry {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// A memory-mapped, read-only view over a PFS0 container.
+pub struct Pfs0 {
+    mmap: Mmap,
+    data_offset: usize,
+    entries: Vec<Pfs0Entry>,
+}
+
+impl Pfs0 {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = fs::File::open(path.as_ref())
+            .with_context(|| format!("failed to open {:?}", path.as_ref()))?;
+        // SAFETY: the file isn't expected to be mutated/truncated by another process while
+        // it's mapped. The bounds checks below use the mapping's length as of this call, so
+        // they do not guard against that: a later truncation makes reads past the new end
+        // of the file fault (SIGBUS), and a concurrent write changes bytes under live slices.
+        let mmap = unsafe { MmapOptions::new().map(&file) }
+            .with_context(|| format!("failed to mmap {:?}", path.as_ref()))?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[0..4] != MAGIC {
+            bail!("{:?} is not a valid PFS0 container", path.as_ref());
+        }
+
+        let entry_count = u32::from_le_bytes(mmap[4..8].try_into().unwrap()) as usize;
+        let string_table_size = u32::from_le_bytes(mmap[8..12].try_into().unwrap()) as usize;
+
+        let string_table_offset = HEADER_SIZE + entry_count * ENTRY_SIZE;
+        let data_offset = string_table_offset + string_table_size;
+
+        if mmap.len() < data_offset {
+            bail!("{:?} is truncated or corrupt", path.as_ref());
+        }
+
+        let mut entries = Vec::with_capacity(entry_count);
+        for i in 0..entry_count {
+            let raw = &mmap[HEADER_SIZE + i * ENTRY_SIZE..HEADER_SIZE + (i + 1) * ENTRY_SIZE];
+            let offset = u64::from_le_bytes(raw[0..8].try_into().unwrap());
+            let size = u64::from_le_bytes(raw[8..16].try_into().unwrap());
+            let name_offset = u32::from_le_bytes(raw[16..20].try_into().unwrap()) as usize;
+
+            let name_start = string_table_offset + name_offset;
+            let name_table = mmap
+                .get(name_start..data_offset)
+                .with_context(|| format!("{:?} has an out-of-bounds entry name", path.as_ref()))?;
+            let name_end = name_table
+                .iter()
+                .position(|&b| b == 0)
+                .with_context(|| format!("{:?} has an unterminated entry name", path.as_ref()))?;
+            let name = String::from_utf8_lossy(&name_table[..name_end]).into_owned();
+
+            let end = data_offset
+                .checked_add(offset as usize)
+                .and_then(|start| start.checked_add(size as usize))
+                .filter(|&end| end <= mmap.len());
+            if end.is_none() {
+                bail!("{:?} has an out-of-bounds entry: {:?}", path.as_ref(), name);
+            }
+
+            entries.push(Pfs0Entry { name, offset, size });
+        }
+
+        Ok(Self {
+            mmap,
+            data_offset,
+            entries,
+        })
+    }
+
+    /// Iterate over the container's entries.
+    pub fn entries(&self) -> impl Iterator<Item = &Pfs0Entry> {
+        self.entries.iter()
+    }
+
+    /// Extract every entry into `dir`, creating it if it doesn't exist.
+    pub fn extract_to<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        fs::create_dir_all(dir.as_ref())?;
+        for entry in self.entries() {
+            let mut components = Path::new(&entry.name).components();
+            if !matches!(components.next(), Some(Component::Normal(_))) || components.next().is_some()
+            {
+                bail!(
+                    "refusing to extract entry with unsafe name: {:?}",
+                    entry.name
+                );
+            }
+
+            let start = self
+                .data_offset
+                .checked_add(entry.offset as usize)
+                .with_context(|| format!("{:?} has an out-of-bounds entry", entry.name))?;
+            let end = start
+                .checked_add(entry.size as usize)
+                .with_context(|| format!("{:?} has an out-of-bounds entry", entry.name))?;
+            let data = self
+                .mmap
+                .get(start..end)
+                .with_context(|| format!("{:?} has an out-of-bounds entry", entry.name))?;
+            fs::write(dir.as_ref().join(&entry.name), data)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn entry_bytes(offset: u64, size: u64, name_offset: u32) -> [u8; ENTRY_SIZE] {
+        let mut entry = [0u8; ENTRY_SIZE];
+        entry[0..8].copy_from_slice(&offset.to_le_bytes());
+        entry[8..16].copy_from_slice(&size.to_le_bytes());
+        entry[16..20].copy_from_slice(&name_offset.to_le_bytes());
+        entry
+    }
+
+    fn header_bytes(entry_count: u32, string_table_size: u32) -> [u8; HEADER_SIZE] {
+        let mut header = [0u8; HEADER_SIZE];
+        header[0..4].copy_from_slice(MAGIC);
+        header[4..8].copy_from_slice(&entry_count.to_le_bytes());
+        header[8..12].copy_from_slice(&string_table_size.to_le_bytes());
+        header
+    }
+
+    fn write_pfs0(dir: &TempDir, name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_single_entry_container() {
+        let dir = TempDir::new("pfs0-test").unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&header_bytes(1, 8));
+        bytes.extend_from_slice(&entry_bytes(0, 4, 0));
+        bytes.extend_from_slice(b"a.tik\0\0\0");
+        bytes.extend_from_slice(b"data");
+
+        let path = write_pfs0(&dir, "test.nsp", &bytes);
+
+        let pfs0 = Pfs0::open(&path).unwrap();
+        let entries: Vec<_> = pfs0.entries().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "a.tik");
+        assert_eq!(entries[0].size, 4);
+    }
+
+    #[test]
+    fn rejects_truncated_container() {
+        let dir = TempDir::new("pfs0-test").unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&header_bytes(1, 8));
+        bytes.extend_from_slice(&entry_bytes(0, 4, 0));
+        // String table and data region are missing entirely.
+
+        let path = write_pfs0(&dir, "truncated.nsp", &bytes);
+
+        assert!(Pfs0::open(&path).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_name_offset() {
+        let dir = TempDir::new("pfs0-test").unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&header_bytes(1, 8));
+        // name_offset points far past the (tiny) string table.
+        bytes.extend_from_slice(&entry_bytes(0, 4, 0xFFFF));
+        bytes.extend_from_slice(b"a.tik\0\0\0");
+        bytes.extend_from_slice(b"data");
+
+        let path = write_pfs0(&dir, "bad-name-offset.nsp", &bytes);
+
+        assert!(Pfs0::open(&path).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_bounds_entry_size() {
+        let dir = TempDir::new("pfs0-test").unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&header_bytes(1, 8));
+        // size claims far more data than the container actually holds.
+        bytes.extend_from_slice(&entry_bytes(0, 0xFFFF, 0));
+        bytes.extend_from_slice(b"a.tik\0\0\0");
+        bytes.extend_from_slice(b"data");
+
+        let path = write_pfs0(&dir, "bad-size.nsp", &bytes);
+
+        assert!(Pfs0::open(&path).is_err());
+    }
+
+    #[test]
+    fn rejects_entry_offset_that_would_overflow() {
+        let dir = TempDir::new("pfs0-test").unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&header_bytes(1, 8));
+        // offset is chosen so that `data_offset + offset` overflows usize arithmetic.
+        bytes.extend_from_slice(&entry_bytes(u64::MAX, 4, 0));
+        bytes.extend_from_slice(b"a.tik\0\0\0");
+        bytes.extend_from_slice(b"data");
+
+        let path = write_pfs0(&dir, "overflow-offset.nsp", &bytes);
+
+        assert!(Pfs0::open(&path).is_err());
+    }
+
+    #[test]
+    fn extract_to_rejects_path_traversal() {
+        let dir = TempDir::new("pfs0-test").unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&header_bytes(1, 24));
+        bytes.extend_from_slice(&entry_bytes(0, 4, 0));
+        bytes.extend_from_slice(b"../../../../etc/passwd\0\0");
+        bytes.extend_from_slice(b"data");
+
+        let path = write_pfs0(&dir, "traversal.nsp", &bytes);
+        let out_dir = TempDir::new("pfs0-test-out").unwrap();
+
+        let pfs0 = Pfs0::open(&path).unwrap();
+        assert!(pfs0.extract_to(out_dir.path()).is_err());
+    }
+}